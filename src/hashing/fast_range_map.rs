@@ -0,0 +1,246 @@
+//! An open-addressing hash table indexed with [`reciprocal_scale_u64`]
+//! instead of `hash % capacity`, so capacity is not constrained to powers of
+//! two and growth can rescale by any ratio rather than only ever doubling.
+//! Collisions are resolved with Robin Hood linear probing, which bounds
+//! probe-length variance regardless of the table's load.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{BuildHasher, BuildHasherDefault, Hash};
+
+use super::scaling::reciprocal_scale_u64;
+
+const MAX_LOAD_FACTOR_PERCENT: u64 = 90;
+
+struct Slot<K, V> {
+    key: K,
+    value: V,
+    probe_distance: usize,
+}
+
+pub struct FastRangeMap<K, V, S = BuildHasherDefault<DefaultHasher>> {
+    buckets: Vec<Option<Slot<K, V>>>,
+    len: usize,
+    hash_builder: S,
+}
+
+impl<K, V> FastRangeMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(cap: usize) -> Self {
+        Self::with_hasher(cap, BuildHasherDefault::default())
+    }
+}
+
+impl<K, V, S> FastRangeMap<K, V, S> {
+    pub fn with_hasher(cap: usize, hash_builder: S) -> Self {
+        let cap = cap.max(1);
+        FastRangeMap {
+            buckets: (0..cap).map(|_| None).collect(),
+            len: 0,
+            hash_builder,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<K, V, S> FastRangeMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn hash_of(&self, key: &K) -> u64 {
+        self.hash_builder.hash_one(key)
+    }
+
+    fn bucket_for(&self, hash: u64) -> usize {
+        reciprocal_scale_u64(hash, self.buckets.len() as u64) as usize
+    }
+
+    /// Grows capacity by 1.5x (a clean re-scale under fastrange, unlike a
+    /// mask change under `hash % capacity`) whenever the next insert would
+    /// push the load factor above [`MAX_LOAD_FACTOR_PERCENT`].
+    fn grow_if_needed(&mut self) {
+        let cap = self.buckets.len() as u64;
+        if (self.len as u64 + 1) * 100 <= MAX_LOAD_FACTOR_PERCENT * cap {
+            return;
+        }
+
+        let new_cap = (self.buckets.len() * 3 / 2).max(self.buckets.len() + 1);
+        let old_buckets =
+            std::mem::replace(&mut self.buckets, (0..new_cap).map(|_| None).collect());
+        self.len = 0;
+
+        for slot in old_buckets.into_iter().flatten() {
+            self.insert(slot.key, slot.value);
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(existing) = self.get_mut(&key) {
+            return Some(std::mem::replace(existing, value));
+        }
+
+        self.grow_if_needed();
+
+        let hash = self.hash_of(&key);
+        let mut slot = Slot {
+            key,
+            value,
+            probe_distance: 0,
+        };
+        let mut idx = self.bucket_for(hash);
+
+        loop {
+            match &mut self.buckets[idx] {
+                None => {
+                    self.buckets[idx] = Some(slot);
+                    self.len += 1;
+                    return None;
+                }
+                Some(occupant) => {
+                    // Robin Hood: the entry with the longer probe distance
+                    // takes the spot and the shorter one moves on, so no
+                    // entry ever waits much longer than the table's average.
+                    if occupant.probe_distance < slot.probe_distance {
+                        std::mem::swap(occupant, &mut slot);
+                    }
+                }
+            }
+            slot.probe_distance += 1;
+            idx = (idx + 1) % self.buckets.len();
+        }
+    }
+
+    fn find_index(&self, key: &K) -> Option<usize> {
+        let hash = self.hash_of(key);
+        let mut idx = self.bucket_for(hash);
+        let mut probe_distance = 0;
+
+        loop {
+            match &self.buckets[idx] {
+                Some(slot) if &slot.key == key => return Some(idx),
+                // Robin Hood invariant: probe distances along a run only
+                // decrease once we pass where `key` would have displaced
+                // something, so we can stop early instead of scanning on.
+                Some(slot) if slot.probe_distance < probe_distance => return None,
+                Some(_) => {}
+                None => return None,
+            }
+            probe_distance += 1;
+            idx = (idx + 1) % self.buckets.len();
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let idx = self.find_index(key)?;
+        Some(&self.buckets[idx].as_ref().unwrap().value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let idx = self.find_index(key)?;
+        Some(&mut self.buckets[idx].as_mut().unwrap().value)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let idx = self.find_index(key)?;
+        let removed = self.buckets[idx].take().unwrap();
+        self.len -= 1;
+
+        // Backward-shift deletion: pull each following entry back one slot
+        // to close the probe-distance gap left by the removal.
+        let mut prev = idx;
+        let mut curr = (idx + 1) % self.buckets.len();
+        while let Some(slot) = &self.buckets[curr] {
+            if slot.probe_distance == 0 {
+                break;
+            }
+            let mut slot = self.buckets[curr].take().unwrap();
+            slot.probe_distance -= 1;
+            self.buckets[prev] = Some(slot);
+            prev = curr;
+            curr = (curr + 1) % self.buckets.len();
+        }
+
+        Some(removed.value)
+    }
+}
+
+impl<K, V> Default for FastRangeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_get() {
+        let mut map = FastRangeMap::new();
+
+        assert_eq!(map.insert("a", 1), None);
+        assert_eq!(map.insert("b", 2), None);
+        assert_eq!(map.insert("a", 11), Some(1));
+
+        assert_eq!(map.get(&"a"), Some(&11));
+        assert_eq!(map.get(&"b"), Some(&2));
+        assert_eq!(map.get(&"c"), None);
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn remove() {
+        let mut map = FastRangeMap::new();
+
+        map.insert("a", 1);
+        map.insert("b", 2);
+
+        assert_eq!(map.remove(&"a"), Some(1));
+        assert_eq!(map.get(&"a"), None);
+        assert_eq!(map.get(&"b"), Some(&2));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.remove(&"a"), None);
+    }
+
+    #[test]
+    fn grows_past_initial_capacity() {
+        let mut map = FastRangeMap::with_capacity(1);
+
+        for i in 0..500 {
+            map.insert(i, i * 2);
+        }
+
+        assert_eq!(map.len(), 500);
+        for i in 0..500 {
+            assert_eq!(map.get(&i), Some(&(i * 2)));
+        }
+    }
+
+    #[test]
+    fn remove_then_reinsert_preserves_lookups() {
+        let mut map = FastRangeMap::with_capacity(8);
+
+        for i in 0..20 {
+            map.insert(i, i);
+        }
+        for i in (0..20).step_by(2) {
+            assert_eq!(map.remove(&i), Some(i));
+        }
+        for i in (1..20).step_by(2) {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+        for i in (0..20).step_by(2) {
+            assert_eq!(map.get(&i), None);
+        }
+    }
+}
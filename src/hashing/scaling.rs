@@ -15,12 +15,89 @@ pub fn reciprocal_scale_u32(val: u32, ep_ro: u32) -> u32 {
     ((val as u64 * ep_ro as u64) >> 32) as u32
 }
 
+#[inline]
+pub fn reciprocal_scale_u64(val: u64, ep_ro: u64) -> u64 {
+    ((val as u128 * ep_ro as u128) >> 64) as u64
+}
+
+#[inline]
+pub fn reciprocal_scale_usize(val: usize, ep_ro: usize) -> usize {
+    reciprocal_scale_u64(val as u64, ep_ro as u64) as usize
+}
+
+/// Draws a bias-free integer in `[0, n)` from `rng` using Lemire's
+/// nearly-divisionless method: the same high-bits-of-a-wide-multiply trick as
+/// [`reciprocal_scale_u64`], plus a single modulo to reject the short tail
+/// that would otherwise skew the result.
+///
+/// - source: <https://lemire.me/blog/2019/06/06/nearly-divisionless-random-integer-generation-on-various-systems/>
+pub fn bounded_uniform_u64(rng: &mut impl FnMut() -> u64, n: u64) -> u64 {
+    assert!(n > 0, "n must be positive");
+
+    loop {
+        let r = rng();
+        let m = r as u128 * n as u128;
+        let low = m as u64;
+
+        if low < n {
+            let threshold = 0u64.wrapping_sub(n) % n;
+            if low < threshold {
+                continue;
+            }
+        }
+
+        return (m >> 64) as u64;
+    }
+}
+
+/// Fisher–Yates shuffle on top of [`bounded_uniform_u64`], so callers get a
+/// correct, modulo-free shuffle primitive instead of reaching for `% len`.
+pub fn shuffle<T>(items: &mut [T], rng: &mut impl FnMut() -> u64) {
+    for i in (1..items.len()).rev() {
+        let j = bounded_uniform_u64(rng, (i + 1) as u64) as usize;
+        items.swap(i, j);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::hint::black_box;
 
     use super::*;
 
+    fn xorshift(seed: &mut u64) -> u64 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed
+    }
+
+    #[test]
+    fn bounded_uniform_stays_in_range() {
+        let mut seed = 0x9E3779B97F4A7C15;
+        let mut rng = || xorshift(&mut seed);
+
+        for n in 1..100u64 {
+            for _ in 0..100 {
+                assert!(bounded_uniform_u64(&mut rng, n) < n);
+            }
+        }
+    }
+
+    #[test]
+    fn shuffle_is_a_permutation() {
+        let mut seed = 42;
+        let mut rng = || xorshift(&mut seed);
+
+        let original: Vec<u32> = (0..20).collect();
+        let mut items = original.clone();
+        shuffle(&mut items, &mut rng);
+
+        let mut sorted = items.clone();
+        sorted.sort();
+        assert_eq!(sorted, original);
+    }
+
     #[bench]
     fn bench_reciprocal_scale_u8(b: &mut test::Bencher) {
         b.iter(|| {
@@ -1,20 +1,114 @@
 //! - source: <https://github.com/torvalds/linux/blob/master/net/sched/sch_netem.c>
+//! - comparator design: <https://github.com/jonhoo/copse>
 
-use std::collections::{BTreeMap, VecDeque};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::iter::Peekable;
+
+/// A total order over `K`, supplied externally so `TFifo` is not limited to
+/// `K: Ord`.
+pub trait Comparator<K> {
+    fn compare(&self, a: &K, b: &K) -> Ordering;
+}
+
+impl<K, F> Comparator<K> for F
+where
+    F: Fn(&K, &K) -> Ordering,
+{
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        self(a, b)
+    }
+}
+
+/// The comparator used by [`TFifo::new`]: defers to `K`'s own [`Ord`] impl.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DefaultComparator;
+
+impl<K: Ord> Comparator<K> for DefaultComparator {
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+/// Flips another comparator, turning a min-first queue into a max-first one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Reverse<C>(pub C);
+
+impl<K, C> Comparator<K> for Reverse<C>
+where
+    C: Comparator<K>,
+{
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        self.0.compare(a, b).reverse()
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct TFifo<K, V> {
-    root: BTreeMap<K, VecDeque<V>>,
+pub struct TFifo<K, V, C = DefaultComparator> {
+    // Sorted by `cmp`. A `Vec` is used instead of a `BTreeMap` because
+    // `BTreeMap` cannot consult external comparator state during its own
+    // comparisons; this can later be swapped for a B-tree port if large
+    // out-of-order working sets make the O(n) shift on insert a bottleneck.
+    root: Vec<(K, VecDeque<V>)>,
     list: VecDeque<(K, V)>,
     len: usize,
+    cmp: C,
+    cap: Option<usize>,
+    drop_policy: DropPolicy,
 }
 
-impl<K, V> TFifo<K, V> {
+/// What [`TFifo::insert`] does with the incoming (or a queued) element once
+/// the queue is at capacity. Mirrors the `limit` behavior of the netem
+/// scheduler this structure is modeled on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DropPolicy {
+    /// Refuse the incoming element; the queue is left untouched.
+    Reject,
+    /// Evict whatever [`TFifo::pop`] would next return.
+    DropFront,
+    /// Evict the largest-key element currently queued.
+    ///
+    /// Identical to [`DropPolicy::DropTail`]; kept as the name under which
+    /// this policy is more commonly known.
+    DropMax,
+    /// Evict the largest-key element currently queued.
+    ///
+    /// An alias for [`DropPolicy::DropMax`] with no behavioral difference,
+    /// kept for parity with the tail-drop terminology used by the netem
+    /// scheduler this structure is modeled on.
+    DropTail,
+}
+
+impl<K, V> TFifo<K, V, DefaultComparator> {
     pub fn new() -> Self {
+        Self::with_comparator(DefaultComparator)
+    }
+
+    /// Creates a queue bounded to `cap` elements, applying `drop_policy`
+    /// whenever [`TFifo::insert`] is called while already at capacity.
+    pub fn with_capacity(cap: usize, drop_policy: DropPolicy) -> Self {
+        Self::with_comparator_and_capacity(DefaultComparator, cap, drop_policy)
+    }
+}
+
+impl<K, V, C> TFifo<K, V, C> {
+    pub fn with_comparator(cmp: C) -> Self {
         TFifo {
-            root: BTreeMap::new(),
+            root: Vec::new(),
             list: VecDeque::new(),
             len: 0,
+            cmp,
+            cap: None,
+            drop_policy: DropPolicy::Reject,
+        }
+    }
+
+    /// Like [`TFifo::with_comparator`], but bounded to `cap` elements.
+    pub fn with_comparator_and_capacity(cmp: C, cap: usize, drop_policy: DropPolicy) -> Self {
+        TFifo {
+            cap: Some(cap),
+            drop_policy,
+            ..Self::with_comparator(cmp)
         }
     }
 
@@ -25,13 +119,76 @@ impl<K, V> TFifo<K, V> {
     pub fn is_empty(&self) -> bool {
         self.len == 0
     }
+
+    /// Iterates in exact pop order without consuming the queue, by merging
+    /// the non-decreasing `list` fast path with the sorted `root` overflow.
+    pub fn iter(&self) -> Iter<'_, K, V, C> {
+        Iter {
+            cmp: &self.cmp,
+            list: self.list.iter().peekable(),
+            root: self.root.iter(),
+            current: None,
+        }
+    }
 }
 
-impl<K, V> TFifo<K, V>
+impl<K, V, C> TFifo<K, V, C>
 where
-    K: Ord + Clone,
+    K: Clone,
+    C: Comparator<K>,
 {
-    pub fn insert(&mut self, key: K, value: V) {
+    /// Removes and yields every element in exact pop order, merging the
+    /// taken `list` and `root` the same way [`TFifo::iter`] does instead of
+    /// repeatedly calling [`TFifo::pop`] (which would re-pay the O(n)
+    /// `root.remove(0)` shift once per emptied root entry).
+    pub fn drain(&mut self) -> Drain<'_, K, V, C> {
+        let remaining = self.len;
+        let list = std::mem::take(&mut self.list);
+        let root = std::mem::take(&mut self.root);
+        self.len = 0;
+
+        Drain {
+            cmp: &self.cmp,
+            list: list.into_iter().peekable(),
+            root: root.into_iter(),
+            current: None,
+            remaining,
+        }
+    }
+
+    /// Inserts `key`/`value`. If the queue is at capacity, applies the
+    /// configured [`DropPolicy`] and returns the element it dropped instead
+    /// (the incoming one for [`DropPolicy::Reject`], or whichever queued
+    /// element the policy evicts to make room).
+    pub fn insert(&mut self, key: K, value: V) -> Option<(K, V)> {
+        if let Some(cap) = self.cap {
+            if self.len >= cap {
+                return match self.drop_policy {
+                    DropPolicy::Reject => Some((key, value)),
+                    DropPolicy::DropFront => match self.pop() {
+                        Some(evicted) => {
+                            self.insert_unchecked(key, value);
+                            Some(evicted)
+                        }
+                        // `cap == 0`: there was nothing to evict, so behave like `Reject`.
+                        None => Some((key, value)),
+                    },
+                    DropPolicy::DropMax | DropPolicy::DropTail => match self.pop_max() {
+                        Some(evicted) => {
+                            self.insert_unchecked(key, value);
+                            Some(evicted)
+                        }
+                        None => Some((key, value)),
+                    },
+                };
+            }
+        }
+
+        self.insert_unchecked(key, value);
+        None
+    }
+
+    fn insert_unchecked(&mut self, key: K, value: V) {
         self.len += 1;
 
         let list_tail = match self.list.back() {
@@ -42,13 +199,53 @@ where
             }
         };
 
-        if key >= *list_tail.0 {
+        if self.cmp.compare(&key, list_tail.0) != Ordering::Less {
             self.list.push_back((key, value));
             return;
         }
 
-        let root_entry = self.root.entry(key).or_insert_with(VecDeque::new);
-        root_entry.push_back(value);
+        match self
+            .root
+            .binary_search_by(|(k, _)| self.cmp.compare(k, &key))
+        {
+            Ok(idx) => self.root[idx].1.push_back(value),
+            Err(idx) => {
+                let mut values = VecDeque::new();
+                values.push_back(value);
+                self.root.insert(idx, (key, values));
+            }
+        }
+    }
+
+    /// Removes and returns the largest-key element currently queued, checking
+    /// the back of `list` and the last `root` entry.
+    fn pop_max(&mut self) -> Option<(K, V)> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.len -= 1;
+
+        let list_is_larger = match (self.list.back(), self.root.last()) {
+            (Some((list_key, _)), Some((root_key, _))) => {
+                self.cmp.compare(list_key, root_key) != Ordering::Less
+            }
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => unreachable!("len == 0 was checked above"),
+        };
+
+        if list_is_larger {
+            return self.list.pop_back();
+        }
+
+        let last = self.root.len() - 1;
+        let value = self.root[last].1.pop_back().unwrap();
+        let key = self.root[last].0.clone();
+        if self.root[last].1.is_empty() {
+            self.root.remove(last);
+        }
+        Some((key, value))
     }
 
     pub fn pop(&mut self) -> Option<(K, V)> {
@@ -59,26 +256,24 @@ where
         self.len -= 1;
 
         // Pop the first value from the list if there are no root entries
-        let mut first_entry = match self.root.first_entry() {
-            Some(entry) => entry,
-            None => return self.list.pop_front(),
-        };
+        if self.root.is_empty() {
+            return self.list.pop_front();
+        }
 
         // Pop the first value from the list if it is smaller than the first root entry
         if let Some((k, _)) = self.list.front() {
-            if k < first_entry.key() {
+            if self.cmp.compare(k, &self.root[0].0) == Ordering::Less {
                 return self.list.pop_front();
             }
         }
 
         // Pop the first value from the first root entry
-        let key = first_entry.key().clone();
-        let values = first_entry.get_mut();
-        let value = values.pop_front().unwrap();
+        let value = self.root[0].1.pop_front().unwrap();
+        let key = self.root[0].0.clone();
 
         // Remove the root entry if it is empty
-        if values.is_empty() {
-            self.root.remove(&key);
+        if self.root[0].1.is_empty() {
+            self.root.remove(0);
         }
 
         Some((key, value))
@@ -90,29 +285,198 @@ where
         }
 
         // Peek the first value from the list if there are no root entries
-        let (key, values) = match self.root.first_key_value() {
-            Some(entry) => entry,
-            None => return self.list.front().map(|(k, v)| (k, v)),
-        };
+        if self.root.is_empty() {
+            return self.list.front().map(|(k, v)| (k, v));
+        }
 
         // Peek the first value from the list if it is smaller than the first root entry
         if let Some((k, _)) = self.list.front() {
-            if k < key {
+            if self.cmp.compare(k, &self.root[0].0) == Ordering::Less {
                 return self.list.front().map(|(k, v)| (k, v));
             }
         }
 
         // Peek the first value from the first root entry
+        let (key, values) = &self.root[0];
         Some((key, values.front().unwrap()))
     }
 }
 
-impl<K, V> Default for TFifo<K, V> {
+impl<K, V> Default for TFifo<K, V, DefaultComparator> {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Borrowing iterator over a [`TFifo`] in pop order. See [`TFifo::iter`].
+pub struct Iter<'a, K, V, C> {
+    cmp: &'a C,
+    list: Peekable<std::collections::vec_deque::Iter<'a, (K, V)>>,
+    root: std::slice::Iter<'a, (K, VecDeque<V>)>,
+    current: Option<(&'a K, Peekable<std::collections::vec_deque::Iter<'a, V>>)>,
+}
+
+impl<'a, K, V, C> Iterator for Iter<'a, K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current.is_none() {
+            self.current = self
+                .root
+                .next()
+                .map(|(k, values)| (k, values.iter().peekable()));
+        }
+
+        let Some((root_key, root_values)) = self.current.as_mut() else {
+            return self.list.next().map(|(k, v)| (k, v));
+        };
+
+        Some(match self.list.peek() {
+            Some((list_key, _)) if self.cmp.compare(list_key, root_key) == Ordering::Less => {
+                self.list.next().map(|(k, v)| (k, v)).unwrap()
+            }
+            _ => {
+                let v = root_values.next().unwrap();
+                let k = *root_key;
+                if root_values.peek().is_none() {
+                    self.current = None;
+                }
+                (k, v)
+            }
+        })
+    }
+}
+
+impl<'a, K, V, C> IntoIterator for &'a TFifo<K, V, C>
+where
+    C: Comparator<K>,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, C>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Owning iterator over a [`TFifo`] in pop order. See [`TFifo::into_iter`].
+pub struct IntoIter<K, V, C> {
+    cmp: C,
+    list: Peekable<std::collections::vec_deque::IntoIter<(K, V)>>,
+    root: std::vec::IntoIter<(K, VecDeque<V>)>,
+    current: Option<(K, Peekable<std::collections::vec_deque::IntoIter<V>>)>,
+}
+
+impl<K, V, C> Iterator for IntoIter<K, V, C>
+where
+    K: Clone,
+    C: Comparator<K>,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current.is_none() {
+            self.current = self
+                .root
+                .next()
+                .map(|(k, values)| (k, values.into_iter().peekable()));
+        }
+
+        let Some((root_key, root_values)) = self.current.as_mut() else {
+            return self.list.next();
+        };
+
+        Some(match self.list.peek() {
+            Some((list_key, _)) if self.cmp.compare(list_key, root_key) == Ordering::Less => {
+                self.list.next().unwrap()
+            }
+            _ => {
+                let v = root_values.next().unwrap();
+                let k = root_key.clone();
+                if root_values.peek().is_none() {
+                    self.current = None;
+                }
+                (k, v)
+            }
+        })
+    }
+}
+
+impl<K, V, C> IntoIterator for TFifo<K, V, C>
+where
+    K: Clone,
+    C: Comparator<K>,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, C>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            cmp: self.cmp,
+            list: self.list.into_iter().peekable(),
+            root: self.root.into_iter(),
+            current: None,
+        }
+    }
+}
+
+/// Draining iterator over a [`TFifo`] in pop order. See [`TFifo::drain`].
+pub struct Drain<'a, K, V, C> {
+    cmp: &'a C,
+    list: Peekable<std::collections::vec_deque::IntoIter<(K, V)>>,
+    root: std::vec::IntoIter<(K, VecDeque<V>)>,
+    current: Option<(K, Peekable<std::collections::vec_deque::IntoIter<V>>)>,
+    remaining: usize,
+}
+
+impl<'a, K, V, C> Iterator for Drain<'a, K, V, C>
+where
+    K: Clone,
+    C: Comparator<K>,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current.is_none() {
+            self.current = self
+                .root
+                .next()
+                .map(|(k, values)| (k, values.into_iter().peekable()));
+        }
+
+        let Some((root_key, root_values)) = self.current.as_mut() else {
+            let item = self.list.next();
+            if item.is_some() {
+                self.remaining -= 1;
+            }
+            return item;
+        };
+
+        let item = match self.list.peek() {
+            Some((list_key, _)) if self.cmp.compare(list_key, root_key) == Ordering::Less => {
+                self.list.next().unwrap()
+            }
+            _ => {
+                let v = root_values.next().unwrap();
+                let k = root_key.clone();
+                if root_values.peek().is_none() {
+                    self.current = None;
+                }
+                (k, v)
+            }
+        };
+        self.remaining -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::hint::black_box;
@@ -191,6 +555,148 @@ mod tests {
         assert!(fifo.is_empty());
     }
 
+    #[test]
+    fn max_first_with_reverse_comparator() {
+        let mut fifo = TFifo::with_comparator(Reverse(DefaultComparator));
+
+        fifo.insert(1, 1);
+        fifo.insert(3, 3);
+        fifo.insert(2, 2);
+
+        assert_eq!(fifo.peek(), Some((&3, &3)));
+        assert_eq!(fifo.pop(), Some((3, 3)));
+        assert_eq!(fifo.peek(), Some((&2, &2)));
+        assert_eq!(fifo.pop(), Some((2, 2)));
+        assert_eq!(fifo.peek(), Some((&1, &1)));
+        assert_eq!(fifo.pop(), Some((1, 1)));
+        assert_eq!(fifo.peek(), None);
+    }
+
+    #[test]
+    fn custom_comparator_on_non_ord_key() {
+        // `f64` is not `Ord`, so this would not compile with `K: Ord`.
+        let mut fifo = TFifo::with_comparator(|a: &f64, b: &f64| a.total_cmp(b));
+
+        fifo.insert(2.0, 2);
+        fifo.insert(1.0, 1);
+        fifo.insert(3.0, 3);
+
+        assert_eq!(fifo.pop(), Some((1.0, 1)));
+        assert_eq!(fifo.pop(), Some((2.0, 2)));
+        assert_eq!(fifo.pop(), Some((3.0, 3)));
+    }
+
+    #[test]
+    fn iter_matches_pop_order() {
+        let mut fifo = TFifo::default();
+
+        fifo.insert(3, 3);
+        fifo.insert(1, 1);
+        fifo.insert(1, 11);
+        fifo.insert(2, 2);
+
+        let collected: Vec<_> = fifo.iter().map(|(k, v)| (*k, *v)).collect();
+
+        let mut popped = Vec::new();
+        while let Some(kv) = fifo.pop() {
+            popped.push(kv);
+        }
+
+        assert_eq!(collected, popped);
+    }
+
+    #[test]
+    fn into_iter_matches_pop_order() {
+        let mut fifo = TFifo::default();
+
+        fifo.insert(3, 3);
+        fifo.insert(1, 1);
+        fifo.insert(1, 11);
+        fifo.insert(2, 2);
+
+        let expected = vec![(1, 1), (1, 11), (2, 2), (3, 3)];
+        let collected: Vec<_> = fifo.into_iter().collect();
+
+        assert_eq!(collected, expected);
+    }
+
+    #[test]
+    fn drain_removes_everything_in_pop_order() {
+        let mut fifo = TFifo::default();
+
+        fifo.insert(3, 3);
+        fifo.insert(1, 1);
+        fifo.insert(2, 2);
+
+        let collected: Vec<_> = fifo.drain().collect();
+
+        assert_eq!(collected, vec![(1, 1), (2, 2), (3, 3)]);
+        assert!(fifo.is_empty());
+    }
+
+    #[test]
+    fn capacity_reject_refuses_new_element() {
+        let mut fifo = TFifo::with_capacity(2, DropPolicy::Reject);
+
+        assert_eq!(fifo.insert(1, 1), None);
+        assert_eq!(fifo.insert(2, 2), None);
+        assert_eq!(fifo.insert(3, 3), Some((3, 3)));
+
+        assert_eq!(fifo.len(), 2);
+        assert_eq!(fifo.pop(), Some((1, 1)));
+        assert_eq!(fifo.pop(), Some((2, 2)));
+    }
+
+    #[test]
+    fn capacity_drop_front_evicts_the_next_pop() {
+        let mut fifo = TFifo::with_capacity(2, DropPolicy::DropFront);
+
+        assert_eq!(fifo.insert(1, 1), None);
+        assert_eq!(fifo.insert(2, 2), None);
+        assert_eq!(fifo.insert(3, 3), Some((1, 1)));
+
+        assert_eq!(fifo.len(), 2);
+        assert_eq!(fifo.pop(), Some((2, 2)));
+        assert_eq!(fifo.pop(), Some((3, 3)));
+    }
+
+    #[test]
+    fn capacity_drop_max_evicts_the_largest_key() {
+        let mut fifo = TFifo::with_capacity(2, DropPolicy::DropMax);
+
+        assert_eq!(fifo.insert(1, 1), None);
+        assert_eq!(fifo.insert(3, 3), None);
+        assert_eq!(fifo.insert(2, 2), Some((3, 3)));
+
+        assert_eq!(fifo.len(), 2);
+        assert_eq!(fifo.pop(), Some((1, 1)));
+        assert_eq!(fifo.pop(), Some((2, 2)));
+    }
+
+    #[test]
+    fn capacity_drop_tail_is_an_alias_for_drop_max() {
+        let mut fifo = TFifo::with_capacity(2, DropPolicy::DropTail);
+
+        assert_eq!(fifo.insert(1, 1), None);
+        assert_eq!(fifo.insert(3, 3), None);
+        assert_eq!(fifo.insert(2, 2), Some((3, 3)));
+
+        assert_eq!(fifo.len(), 2);
+        assert_eq!(fifo.pop(), Some((1, 1)));
+        assert_eq!(fifo.pop(), Some((2, 2)));
+    }
+
+    #[test]
+    fn capacity_zero_always_rejects() {
+        let mut front = TFifo::with_capacity(0, DropPolicy::DropFront);
+        assert_eq!(front.insert(1, 1), Some((1, 1)));
+        assert_eq!(front.len(), 0);
+
+        let mut max = TFifo::with_capacity(0, DropPolicy::DropMax);
+        assert_eq!(max.insert(1, 1), Some((1, 1)));
+        assert_eq!(max.len(), 0);
+    }
+
     const N: usize = 1000;
 
     #[bench]